@@ -0,0 +1,210 @@
+// driver runs the per-height State across multiple heights, so the crate
+// can act as a continuous consensus engine instead of a one-shot round
+// machine.
+
+use crate::evidence::Evidence;
+use crate::vote_keeper::{ValidatorId, ValidatorSet, Vote, VoteKeeper};
+use crate::{Clock, Event, Message, RoundStep, State, Timeout, Value};
+
+// Driver owns the current height's State together with the VoteKeeper that
+// aggregates this height's votes into the Events State::next consumes, so
+// the crate can be fed raw votes directly instead of the caller having to
+// run VoteKeeper itself and thread its output back in.
+pub(crate) struct Driver<V: Value> {
+    state: State<V>,
+    votes: VoteKeeper<V>,
+    validators: ValidatorSet,
+}
+
+impl<V: Value> Driver<V> {
+    pub(crate) fn new(height: i64, validators: ValidatorSet) -> Driver<V> {
+        Driver { state: State::new(height), votes: VoteKeeper::new(validators.clone()), validators }
+    }
+
+    pub(crate) fn state(&self) -> &State<V> {
+        &self.state
+    }
+
+    // record_proposal caches a proposal's value and timestamp against its
+    // round, so a later vote-driven threshold crossing (which only ever
+    // carries the value's Id) can resolve it back to the full value.
+    pub(crate) fn record_proposal(&mut self, round: i64, value: V, timestamp: i64) {
+        self.votes.record_proposal(round, value, timestamp);
+    }
+
+    // apply_vote feeds `vote` into the vote-counting subsystem and, if it
+    // newly crosses a threshold, hands the resulting Event to `apply` so it
+    // drives the state machine exactly as a directly-delivered Event would.
+    // `address` is our own validator id and `proposer`/`own_value` are
+    // forwarded to VoteKeeper::apply to pick RoundSkipProposer over
+    // RoundSkip when we're the skipped-to round's proposer.
+    pub(crate) fn apply_vote(
+        &mut self,
+        vote: Vote<V>,
+        address: ValidatorId,
+        proposer: &dyn Fn(i64) -> ValidatorId,
+        own_value: &dyn Fn() -> V,
+        clock: &dyn Clock,
+        is_valid: &dyn Fn(&V) -> bool,
+    ) -> (Vec<Message<V>>, Option<Evidence<V::Id>>) {
+        let (event, evidence) = self.votes.apply(vote, self.state.round(), address, proposer, own_value);
+        let messages = match event {
+            Some(event) => self.apply(event, clock, is_valid),
+            None => Vec::new(),
+        };
+        (messages, evidence)
+    }
+
+    // apply feeds `event` to the current height's state machine. A Decision
+    // moves us into NewHeight to await the commit-wait interval (so late
+    // precommits for the committed block can still be gossiped and
+    // collected); like every other wait-state transition in State::next,
+    // entering it also schedules a Timeout (step NewHeight) so the caller
+    // knows to deliver the matching TimeoutCommit once the interval elapses.
+    // TimeoutCommit then resets round, step, locked and valid and re-enters
+    // NewRound for height + 1.
+    //
+    // Unlike State::next, this can produce more than one Message for a
+    // single event (the Decision itself, plus the commit-wait Timeout), so
+    // it returns a Vec rather than an Option.
+    pub(crate) fn apply(&mut self, event: Event<V>, clock: &dyn Clock, is_valid: &dyn Fn(&V) -> bool) -> Vec<Message<V>> {
+        if let Event::TimeoutCommit(h) = event {
+            return if h == self.state.height() && self.state.is_step(RoundStep::NewHeight) {
+                self.state = self.state.clone().commit_next_height();
+                // VoteKeeper ingests votes for a single height, so its
+                // per-round latches, proposal cache and evidence history
+                // must not carry over into the next one -- otherwise a
+                // threshold already crossed in round 0 of the previous
+                // height would silently swallow the same legitimate
+                // crossing in round 0 of this one.
+                self.votes = VoteKeeper::new(self.validators.clone());
+                vec![Message::NewRound]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let (state, message) = self.state.clone().next(event, clock, is_valid);
+        self.state = state;
+        let mut messages: Vec<Message<V>> = message.into_iter().collect();
+        if let Some(Message::Decision(_)) = messages.last() {
+            let round = self.state.round();
+            self.state = self.state.clone().set_step(RoundStep::NewHeight);
+            messages.push(Message::Timeout(Timeout::new(round, RoundStep::NewHeight)));
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{FixedClock, TestValue};
+
+    const VALID: &dyn Fn(&TestValue) -> bool = &|_| true;
+
+    fn validators() -> ValidatorSet {
+        ValidatorSet::new(vec![(ValidatorId(1), 1), (ValidatorId(2), 1), (ValidatorId(3), 1)])
+    }
+
+    #[test]
+    fn decision_schedules_commit_wait_then_advances_height() {
+        let clock = FixedClock(100);
+        let mut driver: Driver<TestValue> = Driver::new(1, validators());
+
+        driver.apply(Event::NewRound(0), &clock, VALID);
+        driver.apply(Event::Proposal(0, TestValue(7), 100), &clock, VALID);
+        driver.apply(Event::PolkaValue(0, TestValue(7), 100), &clock, VALID);
+
+        let messages = driver.apply(Event::PrecommitValue(0, TestValue(7), 100), &clock, VALID);
+        assert!(matches!(messages.as_slice(), [Message::Decision(_), Message::Timeout(_)]));
+        assert!(driver.state().is_step(RoundStep::NewHeight));
+        assert_eq!(driver.state().height(), 1);
+
+        let messages = driver.apply(Event::TimeoutCommit(1), &clock, VALID);
+        assert!(matches!(messages.as_slice(), [Message::NewRound]));
+        assert_eq!(driver.state().height(), 2);
+        assert!(driver.state().is_step(RoundStep::NewRound));
+    }
+
+    #[test]
+    fn stale_timeout_commit_is_ignored() {
+        let clock = FixedClock(100);
+        let mut driver: Driver<TestValue> = Driver::new(1, validators());
+        // still at height 1, NewRound step: a TimeoutCommit for a height we
+        // haven't decided yet (or already left) must be a no-op.
+        let messages = driver.apply(Event::TimeoutCommit(1), &clock, VALID);
+        assert!(messages.is_empty());
+        assert_eq!(driver.state().height(), 1);
+    }
+
+    #[test]
+    fn votes_crossing_threshold_drive_the_state_machine_to_decision() {
+        let clock = FixedClock(100);
+        let mut driver: Driver<TestValue> = Driver::new(1, validators());
+        let own_address = ValidatorId(1);
+        let proposer = |_round: i64| ValidatorId(1);
+        let own_value = || TestValue(7);
+
+        driver.apply(Event::NewRound(0), &clock, VALID);
+        driver.record_proposal(0, TestValue(7), 100);
+        driver.apply(Event::Proposal(0, TestValue(7), 100), &clock, VALID);
+
+        // +2/3 prevotes for TestValue(7) should surface as a PolkaValue Event
+        // that drives the state machine into Precommit with a Precommit
+        // message, without the caller ever constructing the Event itself.
+        for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+            let vote = Vote::new(0, RoundStep::Prevote, Some(7), address);
+            let (messages, evidence) = driver.apply_vote(vote, own_address, &proposer, &own_value, &clock, VALID);
+            assert!(evidence.is_none());
+            if address == ValidatorId(3) {
+                assert!(matches!(messages.as_slice(), [Message::Precommit(_)]));
+            }
+        }
+        assert!(driver.state().is_step(RoundStep::Precommit));
+
+        // +2/3 precommits for the same value should then drive a Decision.
+        let mut last_messages = Vec::new();
+        for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+            let vote = Vote::new(0, RoundStep::Precommit, Some(7), address);
+            let (messages, _) = driver.apply_vote(vote, own_address, &proposer, &own_value, &clock, VALID);
+            last_messages = messages;
+        }
+        assert!(matches!(last_messages.as_slice(), [Message::Decision(_), Message::Timeout(_)]));
+    }
+
+    #[test]
+    fn vote_keeper_is_reset_for_the_next_height() {
+        let clock = FixedClock(100);
+        let mut driver: Driver<TestValue> = Driver::new(1, validators());
+        let own_address = ValidatorId(1);
+        let proposer = |_round: i64| ValidatorId(1);
+        let own_value = || TestValue(7);
+
+        // Decide height 1 via round 0 votes, then advance to height 2.
+        driver.apply(Event::NewRound(0), &clock, VALID);
+        driver.record_proposal(0, TestValue(7), 100);
+        driver.apply(Event::Proposal(0, TestValue(7), 100), &clock, VALID);
+        for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+            driver.apply_vote(Vote::new(0, RoundStep::Prevote, Some(7), address), own_address, &proposer, &own_value, &clock, VALID);
+        }
+        for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+            driver.apply_vote(Vote::new(0, RoundStep::Precommit, Some(7), address), own_address, &proposer, &own_value, &clock, VALID);
+        }
+        driver.apply(Event::TimeoutCommit(1), &clock, VALID);
+        assert_eq!(driver.state().height(), 2);
+
+        // Replaying the identical round-0 vote sequence at height 2 must
+        // still cross the +2/3 thresholds: if VoteKeeper carried over
+        // height 1's latches, this would be a silent no-op.
+        driver.apply(Event::NewRound(0), &clock, VALID);
+        driver.record_proposal(0, TestValue(7), 100);
+        driver.apply(Event::Proposal(0, TestValue(7), 100), &clock, VALID);
+        let mut last_messages = Vec::new();
+        for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+            let (messages, _) = driver.apply_vote(Vote::new(0, RoundStep::Prevote, Some(7), address), own_address, &proposer, &own_value, &clock, VALID);
+            last_messages = messages;
+        }
+        assert!(matches!(last_messages.as_slice(), [Message::Precommit(_)]));
+    }
+}
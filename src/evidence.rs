@@ -0,0 +1,152 @@
+// evidence turns faults observed in the vote stream into deterministic,
+// byte-identical-across-honest-nodes proof of a validator's misbehaviour.
+// It operates purely on value Ids (the compact identifier a vote carries),
+// never the full value, so it imposes no extra bound on the value type.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::vote_keeper::ValidatorId;
+use crate::RoundStep;
+
+// Evidence is fork-accountability output, carrying enough of the conflicting
+// votes to let a third party verify the fault independently.
+pub(crate) enum Evidence<Id> {
+    // Equivocation: two distinct votes from the same validator for the same
+    // (round, step).
+    Equivocation {
+        address: ValidatorId,
+        round: i64,
+        step: RoundStep,
+        first: Option<Id>,
+        second: Option<Id>,
+    },
+    // Amnesia: the validator precommitted `from` at `from_round`, then cast
+    // a vote for a different value at `to_round` with no polka for that
+    // value at any round in `missing_pol_range` (from_round, to_round] to
+    // justify the change.
+    Amnesia {
+        address: ValidatorId,
+        from_round: i64,
+        from: Id,
+        to_round: i64,
+        to: Id,
+        missing_pol_range: (i64, i64),
+    },
+}
+
+// EvidenceDetector watches the vote stream for a single height and surfaces
+// amnesia: a lock change by a validator that no polka justifies.
+// Equivocation is detected upstream by VoteKeeper's per-(round, step)
+// dedup, since that's where every validator's vote already passes through;
+// it's folded into the same Evidence type via `observe`'s caller.
+pub(crate) struct EvidenceDetector<Id> {
+    // every precommit-for-a-value cast by each validator this height, in
+    // the order they were observed
+    precommits: HashMap<ValidatorId, Vec<(i64, Id)>>,
+    // rounds at which a polka (+2/3 prevotes) for a value was observed
+    polka_rounds: HashMap<Id, Vec<i64>>,
+}
+
+impl<Id: Clone + PartialEq + Eq + Hash> EvidenceDetector<Id> {
+    pub(crate) fn new() -> EvidenceDetector<Id> {
+        EvidenceDetector {
+            precommits: HashMap::new(),
+            polka_rounds: HashMap::new(),
+        }
+    }
+
+    // record_polka is called whenever a PolkaValue threshold fires, so a
+    // later lock change can be checked against it.
+    pub(crate) fn record_polka(&mut self, round: i64, value: Id) {
+        self.polka_rounds.entry(value).or_default().push(round);
+    }
+
+    // observe checks a prevote or precommit against this validator's
+    // precommit history for amnesia (precommitting A, then prevoting OR
+    // precommitting a different B with no justifying polka), then folds it
+    // into that history if it's itself a precommit for a value.
+    pub(crate) fn observe(&mut self, address: ValidatorId, round: i64, step: RoundStep, value: Option<Id>) -> Option<Evidence<Id>> {
+        if step != RoundStep::Precommit && step != RoundStep::Prevote {
+            return None;
+        }
+        let evidence = self.check_amnesia(address, round, value.clone());
+        if step == RoundStep::Precommit {
+            if let Some(to) = value {
+                self.precommits.entry(address).or_default().push((round, to));
+            }
+        }
+        evidence
+    }
+
+    fn check_amnesia(&self, address: ValidatorId, round: i64, value: Option<Id>) -> Option<Evidence<Id>> {
+        let to = value?;
+        let history = self.precommits.get(&address)?;
+        let (from_round, from) = history.iter().filter(|(r, _)| *r < round).max_by_key(|(r, _)| *r)?;
+        if *from == to {
+            return None; // same value, no lock change
+        }
+        let justified = self
+            .polka_rounds
+            .get(&to)
+            .is_some_and(|rounds| rounds.iter().any(|r| *r > *from_round && *r <= round));
+        if justified {
+            return None;
+        }
+        Some(Evidence::Amnesia {
+            address,
+            from_round: *from_round,
+            from: from.clone(),
+            to_round: round,
+            to,
+            missing_pol_range: (*from_round, round),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amnesia_flagged_on_conflicting_precommit_without_polka() {
+        let mut detector: EvidenceDetector<u64> = EvidenceDetector::new();
+        let addr = ValidatorId(1);
+        assert!(detector.observe(addr, 1, RoundStep::Precommit, Some(10)).is_none());
+        let evidence = detector.observe(addr, 2, RoundStep::Precommit, Some(20));
+        match evidence {
+            Some(Evidence::Amnesia { from_round, from, to_round, to, .. }) => {
+                assert_eq!((from_round, from, to_round, to), (1, 10, 2, 20));
+            }
+            _ => panic!("expected amnesia evidence"),
+        }
+    }
+
+    #[test]
+    fn amnesia_flagged_on_conflicting_prevote_without_polka() {
+        let mut detector: EvidenceDetector<u64> = EvidenceDetector::new();
+        let addr = ValidatorId(1);
+        assert!(detector.observe(addr, 1, RoundStep::Precommit, Some(10)).is_none());
+        let evidence = detector.observe(addr, 2, RoundStep::Prevote, Some(20));
+        assert!(matches!(evidence, Some(Evidence::Amnesia { .. })));
+    }
+
+    #[test]
+    fn amnesia_not_flagged_when_justified_by_polka() {
+        let mut detector: EvidenceDetector<u64> = EvidenceDetector::new();
+        let addr = ValidatorId(1);
+        detector.observe(addr, 1, RoundStep::Precommit, Some(10));
+        detector.record_polka(2, 20);
+        let evidence = detector.observe(addr, 2, RoundStep::Precommit, Some(20));
+        assert!(evidence.is_none());
+    }
+
+    #[test]
+    fn amnesia_not_flagged_for_same_value() {
+        let mut detector: EvidenceDetector<u64> = EvidenceDetector::new();
+        let addr = ValidatorId(1);
+        detector.observe(addr, 1, RoundStep::Precommit, Some(10));
+        let evidence = detector.observe(addr, 2, RoundStep::Precommit, Some(10));
+        assert!(evidence.is_none());
+    }
+}
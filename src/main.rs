@@ -0,0 +1,651 @@
+
+mod driver;
+mod evidence;
+mod vote_keeper;
+
+// testutil holds the minimal Value/Clock fixtures shared by this module's
+// and the sibling modules' (driver, vote_keeper) unit tests.
+#[cfg(test)]
+pub(crate) mod testutil {
+    use super::{Clock, Value};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub(crate) struct TestValue(pub(crate) u64);
+
+    impl Value for TestValue {
+        type Id = u64;
+        fn id(&self) -> u64 {
+            self.0
+        }
+    }
+
+    pub(crate) struct FixedClock(pub(crate) i64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> i64 {
+            self.0
+        }
+    }
+}
+
+// Value is the value the consensus seeks agreement on. Implementors supply
+// a compact, hashable Id so votes can reference a value the way a real vote
+// references a block by hash, without the crate needing to hash (or even
+// fully compare) the value itself.
+pub(crate) trait Value: Clone + PartialEq {
+    // Id must be Ord (not just Hash) so that two conflicting votes can be
+    // canonicalized into a deterministic order: HashMap/HashSet use a
+    // randomized hasher, so sorting by hash would make the same
+    // equivocation produce different-looking Evidence on different nodes.
+    type Id: Clone + PartialEq + Eq + std::hash::Hash + Ord + std::fmt::Debug;
+    fn id(&self) -> Self::Id;
+}
+
+// RoundValue contains a Value, the round it was set, and the proposer
+// timestamp that was agreed alongside it.
+#[derive(Clone)]
+struct RoundValue<V: Value>{
+    round: i64,
+    value: V,
+    time: i64,
+}
+
+// Clock gives the state machine the local wall-clock time. It's passed in
+// rather than read directly (e.g. via SystemTime) so the state machine stays
+// pure and testable.
+pub(crate) trait Clock {
+    fn now(&self) -> i64;
+}
+
+// PRECISION bounds the clock skew we tolerate between the proposer and us.
+const PRECISION: i64 = 10;
+// MSGDELAY bounds how long we expect a proposal to take to reach us.
+const MSGDELAY: i64 = 10;
+
+// is_timely implements the proposer-based timestamp (PBT) timeliness
+// predicate: a proposal's timestamp is timely iff it falls within
+// [now - PRECISION - MSGDELAY, now + PRECISION].
+fn is_timely(timestamp: i64, now: i64) -> bool {
+    now - PRECISION - MSGDELAY <= timestamp && timestamp <= now + PRECISION
+}
+
+// State is the state of the consensus.
+#[derive(Clone)]
+pub(crate) struct State<V: Value>{
+    height: i64,
+    round: i64,
+    step: RoundStep,
+    locked: Option<RoundValue<V>>,
+    valid: Option<RoundValue<V>>,
+    // last_lock_change_round is the round at which `locked` last changed,
+    // i.e. the POL round that justified it. -1 means never locked.
+    last_lock_change_round: i64,
+}
+
+impl<V: Value> State<V>{
+    fn set_round(self, round: i64) -> State<V>{
+        State{
+            round,
+            ..self
+        }
+    }
+
+    pub(crate) fn set_step(self, step: RoundStep) -> State<V>{
+        State{
+            step,
+            ..self
+        }
+    }
+
+    fn set_locked(self, locked: V, time: i64) -> State<V>{
+        State{
+            locked: Some(RoundValue{round: self.round, value: locked, time}),
+            last_lock_change_round: self.round,
+            ..self
+        }
+    }
+
+    fn set_valid(self, valid: V, time: i64) -> State<V>{
+        State{
+            valid: Some(RoundValue{round: self.round, value: valid, time}),
+            ..self
+        }
+    }
+
+}
+
+// RoundStep is the step of the consensus in the round.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum RoundStep {
+    NewRound,
+    Propose,
+    Prevote,
+    Precommit,
+    Commit,
+    // NewHeight is entered right after a Decision, and held for the
+    // commit-wait interval so late precommits for the committed block can
+    // still be gossiped and collected before the driver moves on.
+    NewHeight,
+}
+
+// Event causes a state transition.
+pub(crate) enum Event<V: Value> {
+    NewRound(i64),
+    NewRoundProposer(i64, V),
+    Proposal(i64, V, i64),
+    ProposalPolka(i64, i64, V, i64),
+    PolkaAny(i64),
+    PolkaNil(i64),
+    PolkaValue(i64, V, i64),
+    PrecommitAny(i64),
+    PrecommitValue(i64, V, i64),
+    RoundSkip(i64),
+    RoundSkipProposer(i64, V),
+    TimeoutPropose(i64),
+    TimeoutPrevote(i64),
+    TimeoutPrecommit(i64),
+    TimeoutPrecommitProposer(i64, V),
+    // TimeoutCommit fires once the commit-wait interval for `height` has
+    // elapsed, letting the driver move on to the next height.
+    TimeoutCommit(i64),
+}
+
+// Message is returned.
+pub(crate) enum Message<V: Value> {
+    NewRound,
+    Proposal(Proposal<V>),
+    Prevote(Vote<V>),
+    Precommit(Vote<V>),
+    Timeout(Timeout),
+    Decision(RoundValue<V>),
+    // InvalidLockChange flags a proposal whose pol_round does not justify
+    // unlocking from our current lock, so the caller can audit the fault
+    // instead of the node silently prevoting nil for an unexplained reason.
+    InvalidLockChange{ pol_round: i64, last_lock_change_round: i64 },
+}
+
+struct Proposal<V: Value>{
+    round: i64,
+    value: V,
+    pol_round: i64,
+    timestamp: i64,
+}
+
+impl<V: Value> Proposal<V>{
+    fn new(round: i64, value: V, pol_round: i64, timestamp: i64) -> Proposal<V>{
+        Proposal{
+            round,
+            value,
+            pol_round,
+            timestamp,
+        }
+    }
+}
+
+// Vote is our own outgoing prevote/precommit. It references the value by
+// Id, the way a vote references a block by hash rather than carrying the
+// full block.
+struct Vote<V: Value>{
+    round: i64,
+    value: Option<V::Id>,
+}
+
+impl<V: Value> Vote<V> {
+    fn new(round: i64, value: Option<V::Id>) -> Vote<V>{
+        Vote{
+            round,
+            value,
+        }
+    }
+}
+
+pub(crate) struct Timeout{
+    round: i64,
+    step: RoundStep,
+}
+
+impl Timeout{
+    pub(crate) fn new(round: i64, step: RoundStep) -> Timeout{
+        Timeout{
+            round,
+            step,
+        }
+    }
+
+}
+
+impl<V: Value> State<V>{
+    fn new(height: i64) -> State<V>{
+        State{
+            height,
+            round: 0,
+            step: RoundStep::NewRound,
+            locked: None,
+            valid: None,
+            last_lock_change_round: -1,
+        }
+    }
+
+    pub(crate) fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub(crate) fn round(&self) -> i64 {
+        self.round
+    }
+
+    pub(crate) fn is_step(&self, step: RoundStep) -> bool {
+        self.step == step
+    }
+
+    // commit_next_height moves on from NewHeight once the commit-wait
+    // interval has elapsed: height advances and round, step, locked and
+    // valid reset so the next height starts a fresh NewRound.
+    pub(crate) fn commit_next_height(self) -> State<V> {
+        State::new(self.height + 1)
+    }
+
+    // `is_valid` is the caller-supplied validity predicate: a Proposal's
+    // value is only ever prevoted if it passes this check, replacing the
+    // old pre-classified ProposalInvalid event.
+    pub(crate) fn next(self, event: Event<V>, clock: &dyn Clock, is_valid: &dyn Fn(&V) -> bool) -> (State<V>, Option<Message<V>>) {
+        let round = self.round;
+        let s = self;
+        let now = clock.now();
+        let (s, m) = match (s.step, event) {
+            (RoundStep::NewRound, Event::NewRoundProposer(r, v)) => { handle_new_round_proposer(s, r, v, clock) } // 11/14
+            (RoundStep::NewRound, Event::NewRound(r)) => { handle_new_round(s, r) } // 11/20
+            (RoundStep::Propose, Event::Proposal(r, v, ts)) if round == r => { handle_proposal(s, v, ts, now, is_valid) } // 22
+            (RoundStep::Propose, Event::ProposalPolka(r, vr, v, ts)) if round == r => { handle_proposal_polka(s, vr, v, ts, is_valid) } // 28
+            (RoundStep::Propose, Event::TimeoutPropose(r)) if round == r => { handle_timeout_propose(s) } // 57
+            (RoundStep::Prevote, Event::PolkaAny(r)) if round == r => { handle_polka_any(s) } // 34
+            (RoundStep::Prevote, Event::PolkaNil(r)) if round == r => { handle_polka_nil(s) } // 44
+            (RoundStep::Prevote, Event::PolkaValue(r, v, ts)) if round == r => { handle_polka_value_prevote(s, v, ts) } // 36/37 - only once?
+            (RoundStep::Prevote, Event::TimeoutPrevote(r)) if round == r => { handle_timeout_prevote(s) } // 61
+            (RoundStep::Precommit, Event::PolkaValue(r, v, ts)) if round == r => { handle_polka_value_precommit(s, v, ts) } // 36/42 - only once?
+            (_,                    Event::PrecommitAny(r)) if round == r => { handle_precommit_any(s) } // 47
+            (_,                    Event::PrecommitValue(r, v, ts)) => { handle_precommit_value(s, r, v, ts) } // 49
+            (_,                    Event::RoundSkipProposer(r, v)) if round < r => { handle_new_round_proposer(s, r, v, clock) } // 55
+            (_,                    Event::RoundSkip(r)) if round < r => { handle_new_round(s, r) } // 55
+            (_,                    Event::TimeoutPrecommitProposer(r, v)) if round == r=> { handle_new_round_proposer(s, r+1, v, clock) } // 65
+            (_,                    Event::TimeoutPrecommit(r)) => { handle_new_round(s, r+1) } // 65
+            _ => { (s, None) }
+        };
+        (s, m)
+    }
+}
+
+// we're the proposer. decide a propsal.
+// 11/14
+fn handle_new_round_proposer<V: Value>(s: State<V>, r: i64, v: V, clock: &dyn Clock) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_round(r).set_step(RoundStep::Propose);
+    let (value, round, timestamp) = match s.valid.clone() {
+        Some(rv) => { (rv.value, rv.round, rv.time) } // re-propose the valid value at its original timestamp
+        None    => { (v, -1, clock.now()) }
+    };
+    (s, Some(Message::Proposal(Proposal::new(r, value, round, timestamp))))
+}
+
+
+// we're not the proposer. schedule timeout propose
+// 11/20
+fn handle_new_round<V: Value>(s: State<V>, r: i64) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_round(r).set_step(RoundStep::Propose);
+    let (round, step) = (s.round, s.step);
+    (s, Some(Message::Timeout(Timeout::new(round, step))))
+}
+
+// received a complete proposal with new value - prevote if valid and timely, else nil
+// 22
+fn handle_proposal<V: Value>(s: State<V>, proposed: V, timestamp: i64, now: i64, is_valid: &dyn Fn(&V) -> bool) -> (State<V>, Option<Message<V>>){
+    if !is_valid(&proposed) || !is_timely(timestamp, now) {
+        return handle_proposal_invalid(s);
+    }
+    let s = s.set_step(RoundStep::Prevote);
+    let value = match &s.locked {
+        Some(locked) if locked.value.id() != proposed.id() => { None } // locked on something else
+        _ => { Some(proposed.id()) }
+    };
+    let round = s.round;
+    (s, Some(Message::Prevote(Vote::new(round, value))))
+}
+
+// received a complete proposal for an empty, invalid, or untimely value - prevote nil
+// 22
+fn handle_proposal_invalid<V: Value>(s: State<V>) -> (State<V>, Option<Message<V>>){
+    let s = s.set_step(RoundStep::Prevote);
+    let round = s.round;
+    (s, Some(Message::Prevote(Vote::new(round, None))))
+}
+
+// received a complete proposal with old (polka) value - prevote
+// 28
+fn handle_proposal_polka<V: Value>(s: State<V>, vr: i64, proposed: V, _timestamp: i64, is_valid: &dyn Fn(&V) -> bool) -> (State<V>, Option<Message<V>>) {
+    if !is_valid(&proposed) {
+        return handle_proposal_invalid(s);
+    }
+    let last_lock_change_round = s.last_lock_change_round;
+    let current_round = s.round;
+    let s = s.set_step(RoundStep::Prevote);
+    match &s.locked {
+        Some(locked) if locked.round <= vr => {
+            // unlocking: the claimed POL round must postdate our last lock
+            // change and strictly precede the current round, otherwise the
+            // proposal is trying to justify the unlock with a stale or
+            // not-yet-elapsed/future POL and we can't account for it
+            if vr <= last_lock_change_round || vr >= current_round {
+                return (s, Some(Message::InvalidLockChange{ pol_round: vr, last_lock_change_round }));
+            }
+            let id = proposed.id();
+            let round = s.round;
+            (s, Some(Message::Prevote(Vote::new(round, Some(id)))))
+        }
+        Some(locked) if locked.value.id() == proposed.id() => { // already locked on value
+            let id = proposed.id();
+            let round = s.round;
+            (s, Some(Message::Prevote(Vote::new(round, Some(id)))))
+        }
+        _ => { // otherwise, prevote nil
+            let round = s.round;
+            (s, Some(Message::Prevote(Vote::new(round, None))))
+        }
+    }
+}
+
+// timed out of propose - prevote nil
+// 57
+fn handle_timeout_propose<V: Value>(s: State<V>) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_step(RoundStep::Prevote);
+    let round = s.round;
+    (s, Some(Message::Prevote(Vote::new(round, None))))
+}
+
+// 34
+// NOTE: this should only be called once in a round, per the spec,
+// but it's harmless to schedule more timeouts
+fn handle_polka_any<V: Value>(s: State<V>) -> (State<V>, Option<Message<V>>) {
+    let round = s.round;
+    (s, Some(Message::Timeout(Timeout::new(round, RoundStep::Prevote))))
+}
+
+// 44
+fn handle_polka_nil<V: Value>(s: State<V>) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_step(RoundStep::Precommit);
+    let round = s.round;
+    (s, Some(Message::Precommit(Vote::new(round, None))))
+}
+
+// 36
+// NOTE: only one of these two funcs should ever be called, and only once, in a round
+fn handle_polka_value_prevote<V: Value>(s: State<V>, v: V, timestamp: i64) -> (State<V>, Option<Message<V>>) {
+    let id = v.id();
+    let s = s.set_locked(v.clone(), timestamp).set_valid(v, timestamp).set_step(RoundStep::Precommit);
+    let round = s.round;
+    (s, Some(Message::Precommit(Vote::new(round, Some(id)))))
+}
+
+// 36/42
+fn handle_polka_value_precommit<V: Value>(s: State<V>, v: V, timestamp: i64) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_valid(v, timestamp);
+    (s, None)
+}
+
+// 61
+fn handle_timeout_prevote<V: Value>(s: State<V>) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_step(RoundStep::Precommit);
+    let round = s.round;
+    (s, Some(Message::Precommit(Vote::new(round, None))))
+}
+
+// 47
+fn handle_precommit_any<V: Value>(s: State<V>) -> (State<V>, Option<Message<V>>) {
+    let round = s.round;
+    (s, Some(Message::Timeout(Timeout::new(round, RoundStep::Precommit))))
+}
+
+// 49
+fn handle_precommit_value<V: Value>(s: State<V>, r: i64, v: V, timestamp: i64) -> (State<V>, Option<Message<V>>) {
+    let s = s.set_step(RoundStep::Commit);
+    (s, Some(Message::Decision(RoundValue{round: r, value: v, time: timestamp})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testutil::{FixedClock, TestValue};
+    use super::*;
+
+    const VALID: &dyn Fn(&TestValue) -> bool = &|_| true;
+    const INVALID: &dyn Fn(&TestValue) -> bool = &|_| false;
+
+    fn propose_step(clock: &FixedClock) -> State<TestValue> {
+        let (s, _) = State::new(1).next(Event::NewRound(0), clock, VALID);
+        s
+    }
+
+    #[test]
+    fn timely_valid_proposal_is_prevoted() {
+        let clock = FixedClock(100);
+        let s = propose_step(&clock);
+        let (_, message) = s.next(Event::Proposal(0, TestValue(7), 100), &clock, VALID);
+        assert!(matches!(message, Some(Message::Prevote(Vote { value: Some(7), .. }))));
+    }
+
+    #[test]
+    fn untimely_proposal_prevotes_nil() {
+        let clock = FixedClock(100);
+        let s = propose_step(&clock);
+        let (_, message) = s.next(Event::Proposal(0, TestValue(7), 0), &clock, VALID);
+        assert!(matches!(message, Some(Message::Prevote(Vote { value: None, .. }))));
+    }
+
+    #[test]
+    fn invalid_proposal_prevotes_nil() {
+        let clock = FixedClock(100);
+        let s = propose_step(&clock);
+        let (_, message) = s.next(Event::Proposal(0, TestValue(7), 100), &clock, INVALID);
+        assert!(matches!(message, Some(Message::Prevote(Vote { value: None, .. }))));
+    }
+
+    // locked_at_round_zero drives a state through a full round-0 polka so it
+    // locks on TestValue(7) with last_lock_change_round == 0, then skips
+    // ahead to `round` without going through another polka.
+    fn locked_at_round_zero(clock: &FixedClock, round: i64) -> State<TestValue> {
+        let s = propose_step(clock);
+        let (s, _) = s.next(Event::Proposal(0, TestValue(7), 100), clock, VALID);
+        let (s, _) = s.next(Event::PolkaValue(0, TestValue(7), 100), clock, VALID);
+        let (s, _) = s.next(Event::RoundSkip(round), clock, VALID);
+        s
+    }
+
+    #[test]
+    fn polka_rejects_stale_pol_round() {
+        let clock = FixedClock(100);
+        let s = locked_at_round_zero(&clock, 2);
+        let (_, message) = s.next(Event::ProposalPolka(2, 0, TestValue(8), 100), &clock, VALID);
+        assert!(matches!(message, Some(Message::InvalidLockChange { pol_round: 0, last_lock_change_round: 0 })));
+    }
+
+    #[test]
+    fn polka_accepts_pol_round_strictly_between_lock_and_current() {
+        let clock = FixedClock(100);
+        let s = locked_at_round_zero(&clock, 2);
+        let (_, message) = s.next(Event::ProposalPolka(2, 1, TestValue(8), 100), &clock, VALID);
+        assert!(matches!(message, Some(Message::Prevote(Vote { value: Some(8), .. }))));
+    }
+
+    #[test]
+    fn polka_rejects_pol_round_equal_to_current_round() {
+        let clock = FixedClock(100);
+        let s = locked_at_round_zero(&clock, 2);
+        let (_, message) = s.next(Event::ProposalPolka(2, 2, TestValue(8), 100), &clock, VALID);
+        assert!(matches!(message, Some(Message::InvalidLockChange { pol_round: 2, last_lock_change_round: 0 })));
+    }
+
+    #[test]
+    fn polka_rejects_future_pol_round() {
+        let clock = FixedClock(100);
+        let s = locked_at_round_zero(&clock, 2);
+        let (_, message) = s.next(Event::ProposalPolka(2, 3, TestValue(8), 100), &clock, VALID);
+        assert!(matches!(message, Some(Message::InvalidLockChange { pol_round: 3, last_lock_change_round: 0 })));
+    }
+
+    // a second, independent Value implementation with a non-numeric Id,
+    // demonstrating State/Event/Message are generic over the value type
+    // rather than hardcoded to TestValue's u64 Id.
+    #[derive(Clone, PartialEq)]
+    struct StringValue(String);
+
+    impl Value for StringValue {
+        type Id = String;
+        fn id(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn state_machine_is_generic_over_the_value_type() {
+        let clock = FixedClock(100);
+        let is_valid: &dyn Fn(&StringValue) -> bool = &|_| true;
+        let (s, message) = State::<StringValue>::new(1).next(Event::NewRoundProposer(0, StringValue("block-a".to_string())), &clock, is_valid);
+        assert!(s.is_step(RoundStep::Propose));
+        match message {
+            Some(Message::Proposal(proposal)) => assert_eq!(proposal.value.id(), "block-a"),
+            _ => panic!("expected a Proposal message"),
+        }
+    }
+}
+
+// Demo is a minimal concrete Value for `run`'s demo below, standing in for
+// whatever payload a real caller would instantiate Driver with.
+#[derive(Clone, PartialEq, Debug)]
+struct Demo(u64);
+
+impl Value for Demo {
+    type Id = u64;
+    fn id(&self) -> u64 {
+        self.0
+    }
+}
+
+// SystemClock is the real wall-clock Clock a caller outside a test uses.
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system clock is before the epoch")
+            .as_secs() as i64
+    }
+}
+
+fn describe<V: Value + std::fmt::Debug>(message: &Message<V>) {
+    match message {
+        Message::NewRound => println!("-> NewRound"),
+        Message::Proposal(p) => println!("-> Proposal(round={}, pol_round={}, timestamp={}, value={:?})", p.round, p.pol_round, p.timestamp, p.value),
+        Message::Prevote(v) => println!("-> Prevote(round={}, value={:?})", v.round, v.value),
+        Message::Precommit(v) => println!("-> Precommit(round={}, value={:?})", v.round, v.value),
+        Message::Timeout(t) => println!("-> Timeout(round={}, step={:?})", t.round, t.step),
+        Message::Decision(rv) => println!("-> Decision(round={}, time={})", rv.round, rv.time),
+        Message::InvalidLockChange { pol_round, last_lock_change_round } => {
+            println!("-> InvalidLockChange(pol_round={}, last_lock_change_round={})", pol_round, last_lock_change_round)
+        }
+    }
+}
+
+fn describe_evidence<Id: std::fmt::Debug>(ev: &evidence::Evidence<Id>) -> String {
+    match ev {
+        evidence::Evidence::Equivocation { address, round, step, first, second } => {
+            format!("equivocation address={:?} round={} step={:?} first={:?} second={:?}", address, round, step, first, second)
+        }
+        evidence::Evidence::Amnesia { address, from_round, from, to_round, to, missing_pol_range } => {
+            format!("amnesia address={:?} from_round={} from={:?} to_round={} to={:?} missing_pol_range={:?}", address, from_round, from, to_round, to, missing_pol_range)
+        }
+    }
+}
+
+// run drives a Driver through a single round to a Decision and on into the
+// next height, then separately drives a round that times out at every step
+// and a round-skip that carries a prior lock's POL forward, so this is a
+// real caller of Driver/VoteKeeper/EvidenceDetector/State across the full
+// Event surface rather than just their unit tests.
+fn run() {
+    use driver::Driver;
+    use vote_keeper::{ValidatorId, ValidatorSet, Vote as WeightedVote};
+
+    let validators = || ValidatorSet::new(vec![(ValidatorId(1), 1), (ValidatorId(2), 1), (ValidatorId(3), 1), (ValidatorId(4), 1)]);
+    let clock = SystemClock;
+    let is_valid: &dyn Fn(&Demo) -> bool = &|_| true;
+    let own_address = ValidatorId(1);
+    let proposer = |_round: i64| ValidatorId(1);
+    let own_value = || Demo(1);
+
+    // height 1: the happy path -- we propose, +2/3 prevotes drive a Polka,
+    // +2/3 precommits drive a Decision, and the driver waits out the
+    // commit interval before moving on to the next height.
+    let mut driver: Driver<Demo> = Driver::new(1, validators());
+    let timestamp = clock.now();
+    driver.apply(Event::NewRoundProposer(0, Demo(1)), &clock, is_valid).iter().for_each(describe);
+    driver.record_proposal(0, Demo(1), timestamp);
+    driver.apply(Event::Proposal(0, Demo(1), timestamp), &clock, is_valid).iter().for_each(describe);
+
+    for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3), ValidatorId(4)] {
+        let vote = WeightedVote::new(0, RoundStep::Prevote, Some(1), address);
+        let (messages, evidence) = driver.apply_vote(vote, own_address, &proposer, &own_value, &clock, is_valid);
+        messages.iter().for_each(describe);
+        if let Some(ev) = evidence {
+            println!("-> Evidence({})", describe_evidence(&ev));
+        }
+    }
+    for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3), ValidatorId(4)] {
+        let vote = WeightedVote::new(0, RoundStep::Precommit, Some(1), address);
+        let (messages, evidence) = driver.apply_vote(vote, own_address, &proposer, &own_value, &clock, is_valid);
+        messages.iter().for_each(describe);
+        if let Some(ev) = evidence {
+            println!("-> Evidence({})", describe_evidence(&ev));
+        }
+    }
+    driver.apply(Event::TimeoutCommit(1), &clock, is_valid).iter().for_each(describe);
+    println!("driver now at height {}, round {}", driver.state().height(), driver.state().round());
+
+    // height 2: nobody proposes in time, so round 0 times out at every step
+    // (we are not its proposer) and skips to round 1, of which we are the
+    // proposer -- exercising the caller-driven NewRound/TimeoutPropose/
+    // TimeoutPrevote/TimeoutPrecommitProposer events a timer would fire.
+    let mut timeout_driver: Driver<Demo> = Driver::new(2, validators());
+    timeout_driver.apply(Event::NewRound(0), &clock, is_valid).iter().for_each(describe);
+    timeout_driver.apply(Event::TimeoutPropose(0), &clock, is_valid).iter().for_each(describe);
+    timeout_driver.apply(Event::TimeoutPrevote(0), &clock, is_valid).iter().for_each(describe);
+    // we're round 1's proposer, so we use TimeoutPrecommitProposer instead
+    // of the plain TimeoutPrecommit, carrying our own value onward.
+    timeout_driver.apply(Event::TimeoutPrecommitProposer(0, Demo(2)), &clock, is_valid).iter().for_each(describe);
+    // round 1 itself also fails to decide, and we aren't round 2's proposer.
+    timeout_driver.apply(Event::TimeoutPropose(1), &clock, is_valid).iter().for_each(describe);
+    timeout_driver.apply(Event::TimeoutPrevote(1), &clock, is_valid).iter().for_each(describe);
+    timeout_driver.apply(Event::TimeoutPrecommit(1), &clock, is_valid).iter().for_each(describe);
+
+    // height 3: we lock on a value at round 0 via a Polka but don't reach a
+    // Decision, and skip straight to round 2, whose proposer we are, so we
+    // re-propose the same value citing the round-0 Polka. We then receive a
+    // ProposalPolka for that value citing round 1; lock-change accounting
+    // accepts it because round 1 postdates our last lock change (round 0)
+    // and precedes the current round.
+    let mut polka_driver: Driver<Demo> = Driver::new(3, validators());
+    let timestamp = clock.now();
+    polka_driver.apply(Event::NewRoundProposer(0, Demo(9)), &clock, is_valid).iter().for_each(describe);
+    polka_driver.record_proposal(0, Demo(9), timestamp);
+    polka_driver.apply(Event::Proposal(0, Demo(9), timestamp), &clock, is_valid).iter().for_each(describe);
+    for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+        let vote = WeightedVote::new(0, RoundStep::Prevote, Some(9), address);
+        polka_driver.apply_vote(vote, own_address, &proposer, &own_value, &clock, is_valid).0.iter().for_each(describe);
+    }
+    for address in [ValidatorId(1), ValidatorId(2), ValidatorId(3)] {
+        let vote = WeightedVote::new(2, RoundStep::Prevote, None, address);
+        polka_driver.apply_vote(vote, own_address, &proposer, &own_value, &clock, is_valid).0.iter().for_each(describe);
+    }
+    polka_driver.apply(Event::ProposalPolka(2, 1, Demo(9), timestamp), &clock, is_valid).iter().for_each(describe);
+}
+
+fn main() {
+    run();
+}
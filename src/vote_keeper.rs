@@ -0,0 +1,446 @@
+// vote_keeper turns raw, weighted votes into the high-level Events that
+// drive State::next, so the +2/3 (and +1/3) threshold logic lives in the
+// crate instead of being synthesized by the caller.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::evidence::{Evidence, EvidenceDetector};
+use crate::{Event, RoundStep, Value};
+
+// ValidatorId identifies a validator casting a vote.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct ValidatorId(pub(crate) u64);
+
+// VotingPower is a validator's weight in the validator set.
+pub(crate) type VotingPower = i64;
+
+// ValidatorSet holds the voting power of every validator at this height.
+#[derive(Clone)]
+pub(crate) struct ValidatorSet {
+    powers: HashMap<ValidatorId, VotingPower>,
+}
+
+impl ValidatorSet {
+    pub(crate) fn new(powers: Vec<(ValidatorId, VotingPower)>) -> ValidatorSet {
+        ValidatorSet {
+            powers: powers.into_iter().collect(),
+        }
+    }
+
+    fn power_of(&self, address: ValidatorId) -> VotingPower {
+        *self.powers.get(&address).unwrap_or(&0)
+    }
+
+    fn total_power(&self) -> VotingPower {
+        self.powers.values().sum()
+    }
+}
+
+// Vote is a single signed prevote or precommit from a validator, referencing
+// the value by Id rather than carrying it. `step` is always
+// RoundStep::Prevote or RoundStep::Precommit.
+pub(crate) struct Vote<V: Value> {
+    pub(crate) round: i64,
+    pub(crate) step: RoundStep,
+    pub(crate) value: Option<V::Id>,
+    pub(crate) address: ValidatorId,
+}
+
+impl<V: Value> Vote<V> {
+    pub(crate) fn new(round: i64, step: RoundStep, value: Option<V::Id>, address: ValidatorId) -> Vote<V> {
+        Vote {
+            round,
+            step,
+            value,
+            address,
+        }
+    }
+}
+
+enum Threshold<Id> {
+    Value(Id),
+    Nil,
+    Any,
+}
+
+// EquivocationPair is the (first, second) values of two conflicting votes
+// from the same validator, already canonicalized into a deterministic order.
+type EquivocationPair<Id> = (Option<Id>, Option<Id>);
+
+// VoteSet accumulates the votes cast for a single (round, step) and tracks
+// which threshold events have already fired for it.
+struct VoteSet<Id: Clone + PartialEq + Eq + Hash + Ord> {
+    // first vote seen per validator, for dedup/equivocation detection
+    seen: HashMap<ValidatorId, Option<Id>>,
+    // validators whose equivocation has already been reported, so a
+    // redelivery of the same conflicting vote (or a third, fourth, ...
+    // vote from a validator that already equivocated) doesn't re-emit
+    // Evidence for a fault that's already been surfaced
+    equivocated: HashSet<ValidatorId>,
+    power_for_value: HashMap<Option<Id>, VotingPower>,
+    total_power: VotingPower,
+    any_emitted: bool,
+    nil_emitted: bool,
+    value_emitted: bool,
+}
+
+impl<Id: Clone + PartialEq + Eq + Hash + Ord> Default for VoteSet<Id> {
+    fn default() -> VoteSet<Id> {
+        VoteSet {
+            seen: HashMap::new(),
+            equivocated: HashSet::new(),
+            power_for_value: HashMap::new(),
+            total_power: 0,
+            any_emitted: false,
+            nil_emitted: false,
+            value_emitted: false,
+        }
+    }
+}
+
+impl<Id: Clone + PartialEq + Eq + Hash + Ord> VoteSet<Id> {
+    // seen_vote reports whether `value` is exactly the vote already on file
+    // for `address`, i.e. whether recording it again would be a no-op. The
+    // caller uses this to gate fault detection on first-time-seen votes, so
+    // a gossip-retransmitted duplicate can't re-trigger evidence for a fault
+    // that's already been reported.
+    fn seen_vote(&self, address: ValidatorId, value: &Option<Id>) -> bool {
+        self.seen.get(&address) == Some(value)
+    }
+
+    // add records the vote's power (unless it's a dup or an equivocation)
+    // and returns the most specific threshold newly crossed, if any.
+    // `has_nil_threshold` distinguishes Prevote sets, where crossing 2/3 on
+    // nil alone is its own event (PolkaNil), from Precommit sets, where it
+    // just folds into `Any` (there is no PrecommitNil event).
+    fn add(&mut self, value: Option<Id>, address: ValidatorId, power: VotingPower, total_power: VotingPower, has_nil_threshold: bool) -> (Option<Threshold<Id>>, Option<EquivocationPair<Id>>) {
+        match self.seen.get(&address) {
+            Some(v) if *v == value => return (None, None), // duplicate vote, ignore
+            Some(v) => {
+                // equivocation. Only the first divergence is reported --
+                // once `address` is known to have equivocated, further
+                // conflicting votes (including redeliveries of the very
+                // vote that was already reported) are the same fault, not
+                // a new one, so don't count or re-report them.
+                if !self.equivocated.insert(address) {
+                    return (None, None);
+                }
+                // Order the pair by Id rather than by local arrival order,
+                // so two honest nodes that observe the same two conflicting
+                // votes in different network order still build
+                // byte-identical Evidence.
+                let (first, second) = if *v <= value { (v.clone(), value) } else { (value, v.clone()) };
+                return (None, Some((first, second)));
+            }
+            None => {}
+        }
+        self.seen.insert(address, value.clone());
+        self.total_power += power;
+        *self.power_for_value.entry(value.clone()).or_insert(0) += power;
+
+        let crossed = |power: VotingPower| power * 3 > total_power * 2;
+
+        if let Some(id) = value {
+            if !self.value_emitted && crossed(self.power_for_value[&Some(id.clone())]) {
+                self.value_emitted = true;
+                self.any_emitted = true;
+                return (Some(Threshold::Value(id)), None);
+            }
+        } else if has_nil_threshold && !self.nil_emitted && crossed(self.power_for_value[&None]) {
+            self.nil_emitted = true;
+            self.any_emitted = true;
+            return (Some(Threshold::Nil), None);
+        }
+        if !self.any_emitted && crossed(self.total_power) {
+            self.any_emitted = true;
+            return (Some(Threshold::Any), None);
+        }
+        (None, None)
+    }
+}
+
+// RoundVotes holds the prevotes and precommits cast within a single round.
+struct RoundVotes<Id: Clone + PartialEq + Eq + Hash + Ord> {
+    prevotes: VoteSet<Id>,
+    precommits: VoteSet<Id>,
+}
+
+impl<Id: Clone + PartialEq + Eq + Hash + Ord> Default for RoundVotes<Id> {
+    fn default() -> RoundVotes<Id> {
+        RoundVotes {
+            prevotes: VoteSet::default(),
+            precommits: VoteSet::default(),
+        }
+    }
+}
+
+// VoteKeeper ingests votes for a single height and emits the Events that
+// cross the relevant +2/3 or +1/3 thresholds.
+pub(crate) struct VoteKeeper<V: Value> {
+    validators: ValidatorSet,
+    rounds: HashMap<i64, RoundVotes<V::Id>>,
+    // the value and timestamp carried by the Proposal for (round, id),
+    // recorded by the driver via record_proposal so PolkaValue/
+    // PrecommitValue can carry the full value onward, even though the
+    // votes that drove the threshold only ever carried its Id
+    proposals: HashMap<(i64, V::Id), (V, i64)>,
+    // validators whose vote at a round greater than the current round have
+    // already counted towards a RoundSkip, keyed by that round
+    skip_seen: HashMap<i64, HashSet<ValidatorId>>,
+    skip_power: HashMap<i64, VotingPower>,
+    skip_emitted: HashSet<i64>,
+    evidence: EvidenceDetector<V::Id>,
+}
+
+impl<V: Value> VoteKeeper<V> {
+    pub(crate) fn new(validators: ValidatorSet) -> VoteKeeper<V> {
+        VoteKeeper {
+            validators,
+            rounds: HashMap::new(),
+            proposals: HashMap::new(),
+            skip_seen: HashMap::new(),
+            skip_power: HashMap::new(),
+            skip_emitted: HashSet::new(),
+            evidence: EvidenceDetector::new(),
+        }
+    }
+
+    // record_proposal caches the value and timestamp a proposer attached to
+    // `round`, so a later PolkaValue/PrecommitValue (driven by votes that
+    // only carry the value's Id) can still carry the full value onward.
+    pub(crate) fn record_proposal(&mut self, round: i64, value: V, timestamp: i64) {
+        let id = value.id();
+        self.proposals.insert((round, id), (value, timestamp));
+    }
+
+    // apply records `vote` and returns the Event it causes (if any threshold
+    // was newly crossed) together with any fork-accountability Evidence it
+    // surfaces (equivocation or amnesia). `current_round` is the round the
+    // caller's State is currently in, used to detect RoundSkip.
+    //
+    // `address` is our own validator id and `proposer` is the caller-supplied
+    // proposer-selection predicate for this height, so that when a round
+    // skip fires for a round we'd be the proposer of, `apply` can emit
+    // RoundSkipProposer instead of plain RoundSkip, with `own_value` lazily
+    // supplying the value we'd propose (the same caller-supplied-closure
+    // pattern `is_valid` uses in `State::next`).
+    pub(crate) fn apply(
+        &mut self,
+        vote: Vote<V>,
+        current_round: i64,
+        address: ValidatorId,
+        proposer: &dyn Fn(i64) -> ValidatorId,
+        own_value: &dyn Fn() -> V,
+    ) -> (Option<Event<V>>, Option<Evidence<V::Id>>) {
+        let power = self.validators.power_of(vote.address);
+        let total_power = self.validators.total_power();
+
+        let (vote_round, vote_step, vote_address) = (vote.round, vote.step, vote.address);
+        let round_votes = self.rounds.entry(vote_round).or_default();
+        let (set, has_nil_threshold) = match vote_step {
+            RoundStep::Prevote => (&mut round_votes.prevotes, true),
+            RoundStep::Precommit => (&mut round_votes.precommits, false),
+            _ => return (None, None),
+        };
+
+        // A redelivery of a vote already on file (completely normal under
+        // gossip retransmission) carries no new information, so skip fault
+        // detection for it the same way `set.add` skips re-counting it --
+        // otherwise the same already-reported fault would be re-emitted on
+        // every retransmit, making the number of Evidence items depend on
+        // network timing rather than the fault itself.
+        let amnesia = if set.seen_vote(vote_address, &vote.value) {
+            None
+        } else {
+            self.evidence.observe(vote_address, vote_round, vote_step, vote.value.clone())
+        };
+
+        // Fold this vote into its own round's tally before checking for a
+        // round skip below. The skip check used to return early and never
+        // reach this, silently dropping the vote's power from the Value/
+        // Nil/Any thresholds for its own round -- it still has to count
+        // there even if it's also the vote that pushes the round skip over
+        // 1/3.
+        let (threshold, equivocation) = set.add(vote.value, vote_address, power, total_power, has_nil_threshold);
+        let evidence = amnesia.or_else(|| {
+            equivocation.map(|(first, second)| Evidence::Equivocation {
+                address: vote_address,
+                round: vote_round,
+                step: vote_step,
+                first,
+                second,
+            })
+        });
+
+        if let (RoundStep::Prevote, Some(Threshold::Value(id))) = (vote_step, &threshold) {
+            self.evidence.record_polka(vote_round, id.clone());
+        }
+
+        if vote_round > current_round {
+            let seen = self.skip_seen.entry(vote_round).or_default();
+            if seen.insert(vote_address) {
+                *self.skip_power.entry(vote_round).or_insert(0) += power;
+            }
+            if !self.skip_emitted.contains(&vote_round) && self.skip_power[&vote_round] * 3 > total_power {
+                self.skip_emitted.insert(vote_round);
+                let event = if proposer(vote_round) == address {
+                    Event::RoundSkipProposer(vote_round, own_value())
+                } else {
+                    Event::RoundSkip(vote_round)
+                };
+                return (Some(event), evidence);
+            }
+        }
+
+        // a Value threshold only carries an Id; resolve it back to the full
+        // value via the proposal we cached earlier. If we haven't seen the
+        // proposal yet (the vote outran it), there's nothing to attach the
+        // event to, so we drop it here rather than emit a partial Event.
+        let event = threshold.and_then(|t| match (vote_step, t) {
+            (RoundStep::Prevote, Threshold::Value(id)) => {
+                self.proposals.get(&(vote_round, id)).map(|(v, ts)| Event::PolkaValue(vote_round, v.clone(), *ts))
+            }
+            (RoundStep::Prevote, Threshold::Nil) => Some(Event::PolkaNil(vote_round)),
+            (RoundStep::Prevote, Threshold::Any) => Some(Event::PolkaAny(vote_round)),
+            (RoundStep::Precommit, Threshold::Value(id)) => {
+                self.proposals.get(&(vote_round, id)).map(|(v, ts)| Event::PrecommitValue(vote_round, v.clone(), *ts))
+            }
+            (RoundStep::Precommit, Threshold::Nil) | (RoundStep::Precommit, Threshold::Any) => Some(Event::PrecommitAny(vote_round)),
+            _ => None,
+        });
+
+        (event, evidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TestValue;
+
+    fn validators() -> ValidatorSet {
+        ValidatorSet::new(vec![(ValidatorId(1), 1), (ValidatorId(2), 1), (ValidatorId(3), 1)])
+    }
+
+    #[test]
+    fn redelivering_an_already_seen_vote_does_not_repeat_amnesia_evidence() {
+        let mut keeper: VoteKeeper<TestValue> = VoteKeeper::new(validators());
+        let proposer = |_round: i64| ValidatorId(1);
+        let own_value = || TestValue(0);
+        let addr = ValidatorId(2);
+
+        keeper.apply(Vote::new(0, RoundStep::Precommit, Some(10), addr), 0, ValidatorId(99), &proposer, &own_value);
+        let (_, evidence) = keeper.apply(Vote::new(1, RoundStep::Precommit, Some(20), addr), 1, ValidatorId(99), &proposer, &own_value);
+        assert!(matches!(evidence, Some(Evidence::Amnesia { .. })));
+
+        // the exact same vote arriving again (a gossip retransmit) must not
+        // re-surface evidence for a fault that's already been reported.
+        let (_, evidence) = keeper.apply(Vote::new(1, RoundStep::Precommit, Some(20), addr), 1, ValidatorId(99), &proposer, &own_value);
+        assert!(evidence.is_none());
+    }
+
+    #[test]
+    fn vote_that_crosses_the_round_skip_threshold_still_counts_toward_its_own_round() {
+        // 4 equal-weight validators, all voting for the same value in round
+        // 1 while the caller is still at round 0. The second vote pushes
+        // the round's skip power over 1/3 and returns RoundSkip, but its
+        // voting power must still land in round 1's own VoteSet -- 3 votes
+        // for the same value should cross the 2/3 PolkaValue threshold
+        // without needing a 4th.
+        let validators = ValidatorSet::new(vec![(ValidatorId(1), 1), (ValidatorId(2), 1), (ValidatorId(3), 1), (ValidatorId(4), 1)]);
+        let mut keeper: VoteKeeper<TestValue> = VoteKeeper::new(validators);
+        keeper.record_proposal(1, TestValue(7), 100);
+        let proposer = |_round: i64| ValidatorId(1);
+        let own_value = || TestValue(7);
+
+        let (event, _) = keeper.apply(Vote::new(1, RoundStep::Prevote, Some(7), ValidatorId(1)), 0, ValidatorId(99), &proposer, &own_value);
+        assert!(event.is_none());
+
+        let (event, _) = keeper.apply(Vote::new(1, RoundStep::Prevote, Some(7), ValidatorId(2)), 0, ValidatorId(99), &proposer, &own_value);
+        assert!(matches!(event, Some(Event::RoundSkip(1))));
+
+        let (event, _) = keeper.apply(Vote::new(1, RoundStep::Prevote, Some(7), ValidatorId(3)), 0, ValidatorId(99), &proposer, &own_value);
+        assert!(matches!(event, Some(Event::PolkaValue(1, v, 100)) if v == TestValue(7)));
+    }
+
+    #[test]
+    fn duplicate_vote_is_ignored() {
+        let mut set: VoteSet<u64> = VoteSet::default();
+        let addr = ValidatorId(1);
+        let (threshold, equivocation) = set.add(Some(10), addr, 5, 10, true);
+        assert!(threshold.is_none());
+        assert!(equivocation.is_none());
+        let (threshold, equivocation) = set.add(Some(10), addr, 5, 10, true);
+        assert!(threshold.is_none());
+        assert!(equivocation.is_none());
+    }
+
+    #[test]
+    fn equivocation_is_reported_only_once_per_validator() {
+        let mut set: VoteSet<u64> = VoteSet::default();
+        let addr = ValidatorId(1);
+        set.add(Some(10), addr, 1, 10, true);
+        let (_, equivocation) = set.add(Some(20), addr, 1, 10, true);
+        assert_eq!(equivocation, Some((Some(10), Some(20))));
+
+        // a redelivery of the already-reported conflicting vote, or a third
+        // distinct vote from the same validator, is the same fault, not a
+        // new one -- don't re-report it.
+        let (_, equivocation) = set.add(Some(20), addr, 1, 10, true);
+        assert!(equivocation.is_none());
+        let (_, equivocation) = set.add(Some(30), addr, 1, 10, true);
+        assert!(equivocation.is_none());
+    }
+
+    #[test]
+    fn equivocation_pair_is_canonicalized_regardless_of_arrival_order() {
+        let addr = ValidatorId(1);
+
+        let mut forward: VoteSet<u64> = VoteSet::default();
+        forward.add(Some(10), addr, 1, 10, true);
+        let (_, forward_equivocation) = forward.add(Some(20), addr, 1, 10, true);
+
+        let mut reverse: VoteSet<u64> = VoteSet::default();
+        reverse.add(Some(20), addr, 1, 10, true);
+        let (_, reverse_equivocation) = reverse.add(Some(10), addr, 1, 10, true);
+
+        assert_eq!(forward_equivocation, Some((Some(10), Some(20))));
+        assert_eq!(forward_equivocation, reverse_equivocation);
+    }
+
+    #[test]
+    fn value_threshold_crosses_only_once_over_two_thirds() {
+        let mut set: VoteSet<u64> = VoteSet::default();
+        let (a1, a2) = (ValidatorId(1), ValidatorId(2));
+        let (threshold, _) = set.add(Some(7), a1, 2, 3, true);
+        assert!(threshold.is_none()); // 2/3 exactly, not yet crossed
+        let (threshold, _) = set.add(Some(7), a2, 1, 3, true);
+        assert!(matches!(threshold, Some(Threshold::Value(7))));
+    }
+
+    #[test]
+    fn nil_threshold_only_fires_when_has_nil_threshold() {
+        let mut prevotes: VoteSet<u64> = VoteSet::default();
+        let (a1, a2) = (ValidatorId(1), ValidatorId(2));
+        prevotes.add(None, a1, 2, 3, true);
+        let (threshold, _) = prevotes.add(None, a2, 1, 3, true);
+        assert!(matches!(threshold, Some(Threshold::Nil)));
+
+        let mut precommits: VoteSet<u64> = VoteSet::default();
+        precommits.add(None, a1, 2, 3, false);
+        let (threshold, _) = precommits.add(None, a2, 1, 3, false);
+        assert!(matches!(threshold, Some(Threshold::Any)));
+    }
+
+    #[test]
+    fn any_threshold_crosses_on_split_votes() {
+        let mut set: VoteSet<u64> = VoteSet::default();
+        let (a1, a2) = (ValidatorId(1), ValidatorId(2));
+        let (threshold, _) = set.add(Some(1), a1, 4, 9, true);
+        assert!(threshold.is_none());
+        let (threshold, _) = set.add(Some(2), a2, 4, 9, true);
+        assert!(matches!(threshold, Some(Threshold::Any)));
+    }
+}